@@ -1,21 +1,103 @@
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone, UTC};
+use chrono::{Duration as ChronoDuration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, UTC};
 use chrono::prelude::*;
 use errors::*;
 use futures::{Poll, Stream};
+use futures::task::Task;
 use serde::de::{Deserialize, Deserializer, Error as DeserializeError, SeqVisitor, Visitor};
+use std::cmp::Ordering as TaskOrdering;
+use std::collections::BinaryHeap;
 use std::iter::Cloned;
 use std::ops::Range;
 use std::slice;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 use util::{CarryingUpIterator, MergedIterator};
 
+/// Either kind of occurrence iterator a [`Schedule`] can merge together.
+enum UpcomingIter<'a> {
+    Unit(Iter<'a>),
+    RRule(RRuleIter<'a>),
+}
+
+impl<'a> Iterator for UpcomingIter<'a> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        match *self {
+            UpcomingIter::Unit(ref mut it) => it.next(),
+            UpcomingIter::RRule(ref mut it) => it.next(),
+        }
+    }
+}
+
 pub struct Schedule<'a, Tz: 'a + TimeZone> {
-    upcoming: MergedIterator<Iter<'a>>,
+    upcoming: MergedIterator<UpcomingIter<'a>>,
     next: Option<DateTime<Tz>>,
-    waiting: Arc<AtomicBool>,
+    /// Deadline currently registered with `TimerDriver`, if any (avoids duplicate registration).
+    armed: Option<DateTime<Tz>>,
     tz: &'a Tz,
+    dst_policy: DstPolicy,
+    /// Firings left, or `None` to run forever.
+    count: Option<u32>,
+    /// Stop once the next occurrence would fall at or after this instant.
+    until: Option<DateTime<Tz>>,
+}
+
+/// How to resolve a local time that falls in a DST fold (matched by two instants). A DST gap
+/// (matched by none) is unaffected by this: `Schedule` always rolls forward past it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DstPolicy {
+    /// Fire at the earlier of the two instants (the default).
+    Earliest,
+    /// Fire at the later of the two instants.
+    Latest,
+    /// Don't fire for this occurrence at all.
+    Skip,
+}
+
+impl Default for DstPolicy {
+    fn default() -> Self {
+        DstPolicy::Earliest
+    }
+}
+
+/// Resolves a local time to a single instant, honoring `policy` for DST folds and rolling forward
+/// through a DST gap.
+fn resolve_local<Tz: TimeZone>(tz: &Tz, naive: NaiveDateTime, policy: DstPolicy) -> Option<DateTime<Tz>> {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(dt) => Some(dt),
+        LocalResult::Ambiguous(earlier, later) => match policy {
+            DstPolicy::Earliest => Some(earlier),
+            DstPolicy::Latest => Some(later),
+            DstPolicy::Skip => None,
+        },
+        LocalResult::None => resolve_gap(tz, naive, policy),
+    }
+}
+
+/// `naive` falls in a DST gap; finds the first valid instant immediately after it instead of
+/// overshooting by a flat offset.
+fn resolve_gap<Tz: TimeZone>(tz: &Tz, naive: NaiveDateTime, policy: DstPolicy) -> Option<DateTime<Tz>> {
+    // Bound the search comfortably above any real-world gap width.
+    let mut hi = 6 * 3600;
+    while let LocalResult::None = tz.from_local_datetime(&(naive + ChronoDuration::seconds(hi))) {
+        hi += 6 * 3600;
+        if hi > 48 * 3600 {
+            return None;
+        }
+    }
+
+    // Binary search (to the second) for the gap's trailing edge.
+    let mut lo = 0;
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        match tz.from_local_datetime(&(naive + ChronoDuration::seconds(mid))) {
+            LocalResult::None => lo = mid,
+            _ => hi = mid,
+        }
+    }
+
+    resolve_local(tz, naive + ChronoDuration::seconds(hi), policy)
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -23,52 +105,205 @@ pub struct UnitSchedule {
     wdays: Vec<Wday>,
     hours: Vec<Hour>,
     mins: Vec<Min>,
+    months: Vec<Month>,
+    mdays: Vec<MDay>,
+    secs: Vec<Sec>,
 }
 
 pub struct Iter<'a> {
-    inner: CarryingUpIterator<
-        Cloned<slice::Iter<'a, Wday>>, Cloned<slice::Iter<'a, Hour>>, Cloned<slice::Iter<'a, Min>>
-    >,
+    inner: CarryingUpIterator<Cloned<slice::Iter<'a, Hour>>, Cloned<slice::Iter<'a, Min>>, Cloned<slice::Iter<'a, Sec>>>,
+    months: &'a [Month],
+    wdays: &'a [Wday],
+    mdays: &'a [MDay],
     date: NaiveDate,
+    // Most recently emitted (hour, minute, second); detects wraparound to advance `date`.
+    last_hms: (Hour, Min, Sec),
+    first: bool,
 }
 
 type Wday = u32;
 type Hour = u32;
 type Min = u32;
+type Sec = u32;
+type Month = u32;
+/// A day-of-month. Positive values count from the 1st; negative values count back from the end
+/// of the month (`-1` is the last day).
+type MDay = i32;
+
+/// Whether `date` falls on one of `mdays` (empty matches every date).
+fn mdays_match(mdays: &[MDay], date: NaiveDate) -> bool {
+    if mdays.is_empty() {
+        return true;
+    }
+
+    let dim = days_in_month(date.year(), date.month()) as i32;
+    let day = date.day() as i32;
+
+    mdays.iter().any(|&n| {
+        let resolved = if n < 0 { dim + n + 1 } else { n };
+        resolved == day
+    })
+}
+
+/// Whether some day in `month` (in some year) can satisfy `mdays` (empty always can). Checks both
+/// possible lengths for February and one length for every other month, since that's the only
+/// month whose length varies across years.
+fn mdays_possible_in_month(month: Month, mdays: &[MDay]) -> bool {
+    if mdays.is_empty() {
+        return true;
+    }
+
+    let years: &[i32] = if month == 2 { &[2000, 2001] } else { &[2001] };
+
+    years.iter().any(|&y| {
+        let dim = days_in_month(y, month) as i32;
+        mdays.iter().any(|&n| {
+            let resolved = if n < 0 { dim + n + 1 } else { n };
+            1 <= resolved && resolved <= dim
+        })
+    })
+}
+
+/// A pending wakeup: `task` is unparked once real time reaches `deadline`.
+struct Wakeup {
+    deadline: Instant,
+    task: Task,
+}
+
+impl PartialEq for Wakeup {
+    fn eq(&self, other: &Wakeup) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for Wakeup {}
+
+impl PartialOrd for Wakeup {
+    fn partial_cmp(&self, other: &Wakeup) -> Option<TaskOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Wakeup {
+    fn cmp(&self, other: &Wakeup) -> TaskOrdering {
+        // `BinaryHeap` is a max-heap; reverse the comparison so the *earliest* deadline is the
+        // one popped first.
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+/// Drives every `Schedule`'s wakeups off a single background thread and a min-heap of deadlines.
+struct TimerDriver {
+    pending: Mutex<BinaryHeap<Wakeup>>,
+    woken: Condvar,
+}
+
+impl TimerDriver {
+    /// The process-wide driver, spawned on first use.
+    fn global() -> Arc<TimerDriver> {
+        use std::thread;
+
+        static DRIVER: Mutex<Option<Arc<TimerDriver>>> = Mutex::new(None);
+
+        let mut guard = DRIVER.lock().unwrap();
+
+        if guard.is_none() {
+            let driver = Arc::new(TimerDriver {
+                pending: Mutex::new(BinaryHeap::new()),
+                woken: Condvar::new(),
+            });
+
+            let bg = driver.clone();
+            thread::spawn(move || bg.run());
+
+            *guard = Some(driver);
+        }
+
+        guard.as_ref().unwrap().clone()
+    }
+
+    fn schedule(&self, deadline: Instant, task: Task) {
+        self.pending.lock().unwrap().push(Wakeup { deadline: deadline, task: task });
+        self.woken.notify_one();
+    }
+
+    /// Sleeps until the nearest deadline, unparks everything due, and repeats.
+    fn run(&self) {
+        loop {
+            let mut pending = self.pending.lock().unwrap();
+
+            pending = match pending.peek().map(|w| w.deadline) {
+                None => self.woken.wait(pending).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if deadline > now {
+                        self.woken.wait_timeout(pending, deadline - now).unwrap().0
+                    } else {
+                        pending
+                    }
+                }
+            };
+
+            let now = Instant::now();
+            while pending.peek().map_or(false, |w| w.deadline <= now) {
+                pending.pop().unwrap().task.unpark();
+            }
+        }
+    }
+}
 
 impl<'a, Tz: 'a + TimeZone> Schedule<'a, Tz> {
-    pub fn new<S>(sched: S, time_zone: &'a Tz) -> Self where S: IntoIterator<Item = &'a UnitSchedule> {
+    /// Merges `sched` and `rrules` into a single stream of occurrences, bounded by `count` and/or
+    /// `until` (either may be `None` for an unbounded schedule).
+    pub fn new<S, R>(
+        sched: S, rrules: R, time_zone: &'a Tz, dst_policy: DstPolicy, count: Option<u32>,
+        until: Option<DateTime<Tz>>
+    ) -> Self
+        where S: IntoIterator<Item = &'a UnitSchedule>, R: IntoIterator<Item = &'a RRule>
+    {
         let now = time_zone.from_utc_datetime(&UTC::now().naive_utc()).naive_local();
 
+        let unit_iters = sched.into_iter().map(|us| UpcomingIter::Unit(us.iter_since(now)));
+        let rrule_iters = rrules.into_iter().map(|rr| UpcomingIter::RRule(rr.iter_since(now)));
+
         Schedule {
-            upcoming: MergedIterator::new(sched.into_iter().map(|us| us.iter_since(now))),
+            upcoming: MergedIterator::new(unit_iters.chain(rrule_iters)),
             next: None,
-            waiting: Arc::new(AtomicBool::new(false)),
+            armed: None,
             tz: time_zone,
+            dst_policy: dst_policy,
+            count: count,
+            until: until,
         }
     }
 
-    fn next(&mut self, now: &DateTime<Tz>) -> DateTime<Tz> {
+    /// The next instant to fire at, or `None` if `upcoming` has nothing left that `dst_policy`
+    /// doesn't skip.
+    fn next(&mut self, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
         let tz = self.tz;
+        let policy = self.dst_policy;
         self.upcoming.by_ref()
-            .filter_map(|tm| tz.from_local_datetime(&tm).latest())
+            .filter_map(|tm| resolve_local(tz, tm, policy))
             .find(|tm| tm > now)
-            .unwrap()
     }
 
-    fn set_timer(&mut self, dur: Duration) {
+    /// Whether `next` falls at or after `self.until`, i.e. the schedule is exhausted.
+    fn past_until(&self, next: &DateTime<Tz>) -> bool {
+        self.until.as_ref().map_or(false, |until| next >= until)
+    }
+
+    /// Registers a wakeup `dur` from now with the shared [`TimerDriver`], unless one is already
+    /// pending for `deadline` (a `Stream` is routinely polled more than once before its deadline
+    /// arrives).
+    fn arm_timer(&mut self, deadline: &DateTime<Tz>, dur: Duration) {
         use futures::task;
-        use std::thread;
 
-        let task = task::park();
-        let waiting = self.waiting.clone();
-        waiting.store(true, Ordering::Relaxed);
+        if self.armed.as_ref() == Some(deadline) {
+            return;
+        }
 
-        thread::spawn(move || {
-            thread::sleep(dur);
-            task.unpark();
-            waiting.store(false, Ordering::Release);
-        });
+        TimerDriver::global().schedule(Instant::now() + dur, task::park());
+        self.armed = Some(deadline.clone());
     }
 }
 
@@ -79,32 +314,53 @@ impl<'a, Tz: TimeZone> Stream for Schedule<'a, Tz> {
     fn poll(&mut self) -> Poll<Option<()>, Error> {
         use futures::Async::*;
 
+        if self.count == Some(0) {
+            return Ok(Ready(None));
+        }
+
         let now = self.tz.from_utc_datetime(&UTC::now().naive_utc());
 
         let next = if let Some(ref next) = self.next {
             next.clone()
         } else {
-            let next = self.next(&now);
-            self.next = Some(next.clone());
-            next
+            match self.next(&now) {
+                Some(next) => {
+                    self.next = Some(next.clone());
+                    next
+                }
+                None => return Ok(Ready(None)),
+            }
         };
 
+        if self.past_until(&next) {
+            return Ok(Ready(None));
+        }
+
         if let Ok(dur) = next.signed_duration_since(now.clone()).to_std() {
-            if !self.waiting.load(Ordering::Acquire) {
-                self.set_timer(dur);
-            }
+            self.arm_timer(&next, dur);
             Ok(NotReady)
         } else {
-            let next = self.next(&now);
+            let next = match self.next(&now) {
+                Some(next) => next,
+                None => return Ok(Ready(None)),
+            };
+            if self.past_until(&next) {
+                return Ok(Ready(None));
+            }
             self.next = Some(next.clone());
-            self.set_timer(next.signed_duration_since(now).to_std().unwrap());
+            let dur = next.signed_duration_since(now).to_std().unwrap();
+            self.arm_timer(&next, dur);
+            self.count = self.count.map(|n| n - 1);
             Ok(Ready(Some(())))
         }
     }
 }
 
 impl UnitSchedule {
-    pub fn new(mut wdays: Vec<Wday>, mut hours: Vec<Hour>, mut mins: Vec<Min>) -> Option<Self> {
+    pub fn new(
+        mut wdays: Vec<Wday>, mut hours: Vec<Hour>, mut mins: Vec<Min>, mut months: Vec<Month>,
+        mut mdays: Vec<MDay>, mut secs: Vec<Sec>
+    ) -> Option<Self> {
         macro_rules! regularize {
             ($vec:ident, $range:expr) => {
                 if $vec.is_empty() {
@@ -124,53 +380,92 @@ impl UnitSchedule {
         regularize!(wdays, 0..7);
         regularize!(hours, 0..24);
         regularize!(mins, 0..60);
+        regularize!(months, 1..13);
+
+        mdays.sort();
+        mdays.dedup();
+        if mdays.iter().any(|&n| n == 0 || n.checked_abs().map_or(true, |a| a > 31)) {
+            return None;
+        }
+        // Rejects e.g. `months = [2], mdays = [30]`, which would otherwise spin `date_matches`
+        // forever.
+        if !months.iter().all(|&m| mdays_possible_in_month(m, &mdays)) {
+            return None;
+        }
+
+        // An empty `secs` means "every minute", not "every second", so it regularizes to `[0]`.
+        if secs.is_empty() {
+            secs.push(0);
+        } else {
+            secs.sort();
+            secs.dedup();
+            if *secs.last().unwrap() >= 60 {
+                return None;
+            }
+        }
 
         Some(UnitSchedule {
             wdays: wdays,
             hours: hours,
             mins: mins,
+            months: months,
+            mdays: mdays,
+            secs: secs,
         })
     }
 
+    /// Whether `date` satisfies `months`, `wdays` and `mdays`, independent of time of day.
+    fn date_matches(&self, date: NaiveDate) -> bool {
+        self.months.contains(&date.month())
+            && self.wdays.contains(&date.weekday().num_days_from_sunday())
+            && mdays_match(&self.mdays, date)
+    }
+
     pub fn iter_since(&self, since: NaiveDateTime) -> Iter {
         let mut since_date = since.date();
         let mut since_time = since.time();
 
-        if (*self.hours.last().unwrap(), *self.mins.last().unwrap()) <= (since.hour(), since.minute()) {
+        let last = (*self.hours.last().unwrap(), *self.mins.last().unwrap(), *self.secs.last().unwrap());
+        if last <= (since.hour(), since.minute(), since.second()) {
             since_date = since_date.succ();
             since_time = NaiveTime::from_hms(0, 0, 0);
         }
 
-        while !self.wdays.contains(&since_date.weekday().num_days_from_sunday()) {
+        while !self.date_matches(since_date) {
             since_date = since_date.succ();
             since_time = NaiveTime::from_hms(0, 0, 0);
         }
 
         let mut inner = CarryingUpIterator::new(
-            self.wdays.iter().cloned(), self.hours.iter().cloned(), self.mins.iter().cloned()
+            self.hours.iter().cloned(), self.mins.iter().cloned(), self.secs.iter().cloned()
         ).unwrap();
 
         // Proceed `inner`'s iteration state to the first point after `since`:
 
-        let since_whm = (since_date.weekday().num_days_from_sunday(), since_time.hour(), since_time.minute());
-        let mut before_start = (*self.wdays.last().unwrap(), *self.hours.last().unwrap(), *self.mins.last().unwrap());
+        let since_hms = (since_time.hour(), since_time.minute(), since_time.second());
+        let mut before_start = last;
 
-        for whm in inner.by_ref() {
-            if whm > since_whm {
+        for hms in inner.by_ref() {
+            if hms > since_hms {
                 break;
             }
-            before_start = whm;
+            before_start = hms;
         }
 
-        for whm in inner.by_ref() {
-            if whm == before_start {
+        for hms in inner.by_ref() {
+            if hms == before_start {
                 break;
             }
         }
 
         Iter {
             inner: inner,
+            months: &self.months,
+            wdays: &self.wdays,
+            mdays: &self.mdays,
             date: since_date,
+            last_hms: before_start,
+            first: true,
         }
     }
 }
@@ -287,17 +582,111 @@ impl Deserialize for UnitSchedule {
             d.deserialize_seq(NumsVisitor)
         }
 
-        #[derive(Deserialize)]
-        struct Unit(
-            #[serde(deserialize_with = "deserialize_nums")] Vec<Wday>,
-            #[serde(deserialize_with = "deserialize_nums")] Vec<Hour>,
-            #[serde(deserialize_with = "deserialize_nums")] Vec<Min>,
-        );
+        fn deserialize_signed_nums<D: Deserializer>(d: D) -> Result<Vec<i32>, D::Error> {
+            use std::fmt;
+
+            struct SignedNumsVisitor;
+
+            macro_rules! visit_as_i32 {
+                ($name:ident, $t:ty) => {
+                    #[allow(unused_comparisons)]
+                    fn $name<E: DeserializeError>(self, n: $t) -> Result<Vec<i32>, E> {
+                        if ::std::i32::MIN as i64 <= n as i64 && n as i64 <= ::std::i32::MAX as i64 {
+                            Ok(vec![n as i32])
+                        } else {
+                            Err(E::custom(format!("i32 out of range: {}", n)))
+                        }
+                    }
+                }
+            }
+
+            impl Visitor for SignedNumsVisitor {
+                type Value = Vec<i32>;
 
-        let ret = Unit::deserialize(d)?;
+                fn visit_seq<V: SeqVisitor>(self, mut v: V) -> Result<Vec<i32>, V::Error> {
+                    let mut ret = Vec::with_capacity(v.size_hint().0);
+
+                    while let Some(n) = v.visit::<i32>()? {
+                        ret.push(n);
+                    }
+
+                    Ok(ret)
+                }
+
+                fn visit_i32<E>(self, n: i32) -> Result<Vec<i32>, E> {
+                    Ok(vec![n])
+                }
+
+                visit_as_i32!(visit_u64, u64);
+                visit_as_i32!(visit_u32, u32);
+                visit_as_i32!(visit_u16, u16);
+                visit_as_i32!(visit_u8, u8);
+                visit_as_i32!(visit_i64, i64);
+                visit_as_i32!(visit_i16, i16);
+                visit_as_i32!(visit_i8, i8);
+
+                fn visit_unit<E>(self) -> Result<Vec<i32>, E> {
+                    Ok(Vec::new())
+                }
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "an integer or an array of integers")
+                }
+            }
+
+            d.deserialize_seq(SignedNumsVisitor)
+        }
+
+        struct NumList(Vec<u32>);
+
+        impl Deserialize for NumList {
+            fn deserialize<D: Deserializer>(d: D) -> Result<Self, D::Error> {
+                deserialize_nums(d).map(NumList)
+            }
+        }
+
+        struct SignedNumList(Vec<i32>);
+
+        impl Deserialize for SignedNumList {
+            fn deserialize<D: Deserializer>(d: D) -> Result<Self, D::Error> {
+                deserialize_signed_nums(d).map(SignedNumList)
+            }
+        }
+
+        struct UnitScheduleVisitor;
+
+        impl Visitor for UnitScheduleVisitor {
+            type Value = UnitSchedule;
+
+            fn visit_seq<V: SeqVisitor>(self, mut v: V) -> Result<UnitSchedule, V::Error> {
+                let missing = || V::Error::custom("expected 5 elements: wdays, hours, mins, months, mdays");
+
+                let wdays = v.visit::<NumList>()?.ok_or_else(missing)?.0;
+                let hours = v.visit::<NumList>()?.ok_or_else(missing)?.0;
+                let mins = v.visit::<NumList>()?.ok_or_else(missing)?.0;
+                let months = v.visit::<NumList>()?.ok_or_else(missing)?.0;
+                let mdays = v.visit::<SignedNumList>()?.ok_or_else(missing)?.0;
+                // Optional trailing element; 5-element configs omit it.
+                let secs = v.visit::<NumList>()?.map(|l| l.0).unwrap_or_default();
+
+                UnitSchedule::new(wdays, hours, mins, months, mdays, secs)
+                    .ok_or_else(|| V::Error::custom("invalid number"))
+            }
+
+            fn visit_str<E: DeserializeError>(self, s: &str) -> Result<UnitSchedule, E> {
+                parse_schedule(s).map_err(|e| E::custom(e.to_string()))
+            }
 
-        UnitSchedule::new(ret.0, ret.1, ret.2)
-            .ok_or_else(|| D::Error::custom("invalid number"))
+            fn visit_string<E: DeserializeError>(self, s: String) -> Result<UnitSchedule, E> {
+                self.visit_str(&s)
+            }
+
+            fn expecting(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                write!(f, "an array of schedule fields or a schedule phrase (e.g. \"every weekday at 8:00\")")
+            }
+        }
+
+        d.deserialize(UnitScheduleVisitor)
     }
 }
 
@@ -305,13 +694,25 @@ impl<'a> Iterator for Iter<'a> {
     type Item = NaiveDateTime;
 
     fn next(&mut self) -> Option<NaiveDateTime> {
-        let (wday, hr, min) = self.inner.next().unwrap();
+        let (hr, min, sec) = self.inner.next().unwrap();
+
+        if !self.first && (hr, min, sec) <= self.last_hms {
+            loop {
+                self.date = self.date.succ();
 
-        while self.date.weekday().num_days_from_sunday() != wday {
-            self.date = self.date.succ();
+                let matches = self.months.contains(&self.date.month())
+                    && self.wdays.contains(&self.date.weekday().num_days_from_sunday())
+                    && mdays_match(self.mdays, self.date);
+
+                if matches {
+                    break;
+                }
+            }
         }
+        self.first = false;
+        self.last_hms = (hr, min, sec);
 
-        Some(self.date.and_hms(hr, min, 0))
+        Some(self.date.and_hms(hr, min, sec))
     }
 }
 
@@ -330,16 +731,596 @@ fn parse_range(s: &str) -> ::std::result::Result<Range<u32>, ()> {
     }
 }
 
+/// Parses a natural-language schedule phrase, e.g. `"every weekday at 8:00 and 12:40"`,
+/// `"hourly"` or `"every 15 minutes on Mon,Wed"`, into a [`UnitSchedule`].
+///
+/// Grammar: an interval spec (`secondly`/`minutely`/`hourly`/`daily`/`weekly`,
+/// `every weekday`, or `every <N> <unit>`), followed by an optional `on <weekday list>` and an
+/// optional `at <time list>`.
+///
+/// The `at` times are split into a list of hours and a list of minutes, and every hour combines
+/// with every minute: `at 8:00 and 12:40` fires at 8:00, 8:40, 12:00 and 12:40.
+pub fn parse_schedule(s: &str) -> Result<UnitSchedule> {
+    let s = s.trim().to_lowercase();
+
+    let on_pos = s.find(" on ");
+    let at_pos = s.find(" at ");
+
+    if let (Some(on), Some(at)) = (on_pos, at_pos) {
+        if on > at {
+            return Err("\"on\" must come before \"at\"".into());
+        }
+    }
+
+    let head_end = on_pos.or(at_pos).unwrap_or_else(|| s.len());
+    let on_part = on_pos.map(|i| {
+        let end = at_pos.filter(|&a| a > i).unwrap_or_else(|| s.len());
+        &s[i + 4..end]
+    });
+    let at_part = at_pos.map(|i| &s[i + 4..]);
+
+    let head = &s[..head_end];
+    let mut words = head.split_whitespace();
+    let first = words.next().ok_or("empty schedule")?;
+
+    let (mut wdays, mut hours, mut mins, mut secs) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let mut needs_wday = false;
+
+    match first {
+        "secondly" => secs = (0..60).collect(),
+        "minutely" => {}
+        "hourly" => mins = vec![0],
+        "daily" => {
+            hours = vec![0];
+            mins = vec![0];
+        }
+        "weekly" => {
+            hours = vec![0];
+            mins = vec![0];
+            needs_wday = true;
+        }
+        "every" => {
+            let arg = words.next().ok_or("expected a number or \"weekday\" after \"every\"")?;
+
+            if arg == "weekday" {
+                wdays = vec![1, 2, 3, 4, 5];
+            } else {
+                let n: u32 = arg.parse().map_err(|_| format!("expected a number after \"every\", got {}", arg))?;
+                if n == 0 {
+                    return Err("every 0 ... doesn't make sense".into());
+                }
+
+                let unit = words.next().ok_or("expected a unit after the number")?;
+                match singular(unit) {
+                    "second" => secs = progression(n, 60),
+                    "minute" => mins = progression(n, 60),
+                    "hour" => {
+                        hours = progression(n, 24);
+                        mins = vec![0];
+                    }
+                    "day" if n == 1 => {
+                        hours = vec![0];
+                        mins = vec![0];
+                    }
+                    "day" => return Err(format!("every {} days isn't representable (no day-interval support)", n).into()),
+                    other => return Err(format!("unknown unit: {}", other).into()),
+                }
+            }
+        }
+        _ => return Err(format!("unknown interval: {}", first).into()),
+    }
+
+    if let Some(part) = on_part {
+        wdays = split_list(part).into_iter().map(parse_wday_token).collect::<Result<_>>()?;
+    }
+
+    if needs_wday && wdays.is_empty() {
+        return Err("\"weekly\" needs an \"on <weekday>\" clause, e.g. \"weekly on mon\"".into());
+    }
+
+    if let Some(part) = at_part {
+        let times: Vec<(Hour, Min)> = split_list(part).into_iter().map(parse_time_token).collect::<Result<_>>()?;
+        hours = times.iter().map(|&(h, _)| h).collect();
+        mins = times.iter().map(|&(_, m)| m).collect();
+    }
+
+    UnitSchedule::new(wdays, hours, mins, Vec::new(), Vec::new(), secs).ok_or_else(|| "invalid schedule".into())
+}
+
+/// Strips a single trailing `'s'`, e.g. `"minutes"` -> `"minute"`.
+fn singular(word: &str) -> &str {
+    if word.len() > 1 && word.ends_with('s') {
+        &word[..word.len() - 1]
+    } else {
+        word
+    }
+}
+
+/// The arithmetic progression `0, n, 2n, ...` within `0..bound`.
+fn progression(n: u32, bound: u32) -> Vec<u32> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bound {
+        out.push(i);
+        i += n;
+    }
+    out
+}
+
+/// Splits a comma/whitespace separated list, dropping the literal "and".
+fn split_list(s: &str) -> Vec<&str> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|tok| !tok.is_empty() && *tok != "and")
+        .collect()
+}
+
+fn parse_wday_token(tok: &str) -> Result<Wday> {
+    if tok.len() < 3 {
+        return Err(format!("unknown weekday: {}", tok).into());
+    }
+
+    match &tok[..3] {
+        "sun" => Ok(0),
+        "mon" => Ok(1),
+        "tue" => Ok(2),
+        "wed" => Ok(3),
+        "thu" => Ok(4),
+        "fri" => Ok(5),
+        "sat" => Ok(6),
+        _ => Err(format!("unknown weekday: {}", tok).into()),
+    }
+}
+
+fn parse_time_token(tok: &str) -> Result<(Hour, Min)> {
+    let i = tok.find(':').ok_or_else(|| format!("expected H:MM, got {}", tok))?;
+    let (h, m) = tok.split_at(i);
+    let m = &m[1..];
+
+    let h: u32 = h.parse().map_err(|_| format!("expected H:MM, got {}", tok))?;
+    let m: u32 = m.parse().map_err(|_| format!("expected H:MM, got {}", tok))?;
+
+    if h >= 24 || m >= 60 {
+        return Err(format!("time out of range: {}", tok).into());
+    }
+
+    Ok((h, m))
+}
+
+/// The `FREQ` of an [`RRule`], from least to most frequent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freq {
+    Yearly,
+    Monthly,
+    Weekly,
+    Daily,
+    Hourly,
+    Minutely,
+    Secondly,
+}
+
+impl Freq {
+    fn from_str(s: &str) -> ::std::result::Result<Freq, ()> {
+        match s {
+            "YEARLY" => Ok(Freq::Yearly),
+            "MONTHLY" => Ok(Freq::Monthly),
+            "WEEKLY" => Ok(Freq::Weekly),
+            "DAILY" => Ok(Freq::Daily),
+            "HOURLY" => Ok(Freq::Hourly),
+            "MINUTELY" => Ok(Freq::Minutely),
+            "SECONDLY" => Ok(Freq::Secondly),
+            _ => Err(()),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Freq::Yearly => "YEARLY",
+            Freq::Monthly => "MONTHLY",
+            Freq::Weekly => "WEEKLY",
+            Freq::Daily => "DAILY",
+            Freq::Hourly => "HOURLY",
+            Freq::Minutely => "MINUTELY",
+            Freq::Secondly => "SECONDLY",
+        }
+    }
+}
+
+/// An RFC 5545 recurrence rule, parsed from a `RRULE:` value such as
+/// `FREQ=WEEKLY;BYDAY=MO,WE;BYHOUR=8,12;BYMINUTE=20,40`. Only `FREQ` values of
+/// `YEARLY`/`MONTHLY`/`WEEKLY`/`DAILY` are supported, and `INTERVAL` other than `1` only with
+/// `FREQ=YEARLY`/`MONTHLY`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RRule {
+    freq: Freq,
+    interval: u32,
+    bymonth: Vec<u32>,
+    bymonthday: Vec<i32>,
+    byday: Vec<Wday>,
+    byhour: Vec<Hour>,
+    byminute: Vec<Min>,
+    bysecond: Vec<u32>,
+    count: Option<u32>,
+    until: Option<NaiveDateTime>,
+}
+
+impl RRule {
+    /// Parses an RFC 5545 `RRULE` value (the part after `RRULE:`, if any).
+    pub fn from_rfc5545(s: &str) -> Result<RRule> {
+        let mut freq = None;
+        let mut interval = 1;
+        let mut bymonth = Vec::new();
+        let mut bymonthday = Vec::new();
+        let mut byday = Vec::new();
+        let mut byhour = Vec::new();
+        let mut byminute = Vec::new();
+        let mut bysecond = Vec::new();
+        let mut count = None;
+        let mut until = None;
+
+        for part in s.trim().split(';').filter(|p| !p.is_empty()) {
+            let i = part.find('=').ok_or_else(|| format!("malformed RRULE part: {}", part))?;
+            let (key, value) = part.split_at(i);
+            let value = &value[1..];
+
+            match key {
+                "FREQ" => freq = Some(Freq::from_str(value).map_err(|_| format!("unknown FREQ: {}", value))?),
+                "INTERVAL" => interval = value.parse().map_err(|_| format!("bad INTERVAL: {}", value))?,
+                "BYMONTH" => bymonth = parse_csv(value, |n| if 1 <= n && n <= 12 { Some(n) } else { None })?,
+                "BYMONTHDAY" => bymonthday = parse_csv(value, |n: i32| match n.checked_abs() {
+                    Some(a) if 1 <= a && a <= 31 => Some(n),
+                    _ => None,
+                })?,
+                "BYDAY" => byday = value.split(',').map(parse_weekday).collect::<Result<_>>()?,
+                "BYHOUR" => byhour = parse_csv(value, |n| if n < 24 { Some(n) } else { None })?,
+                "BYMINUTE" => byminute = parse_csv(value, |n| if n < 60 { Some(n) } else { None })?,
+                "BYSECOND" => bysecond = parse_csv(value, |n| if n < 60 { Some(n) } else { None })?,
+                "COUNT" => count = Some(value.parse().map_err(|_| format!("bad COUNT: {}", value))?),
+                "UNTIL" => until = Some(parse_until(value)?),
+                _ => {}
+            }
+        }
+
+        let freq = freq.ok_or("RRULE is missing FREQ")?;
+
+        match freq {
+            Freq::Hourly | Freq::Minutely | Freq::Secondly => {
+                return Err(format!("FREQ={} is not supported", freq.as_str()).into());
+            }
+            Freq::Yearly | Freq::Monthly | Freq::Weekly | Freq::Daily => {}
+        }
+        if interval != 1 && freq != Freq::Yearly && freq != Freq::Monthly {
+            return Err(
+                format!("INTERVAL is only supported with FREQ=YEARLY or FREQ=MONTHLY, not FREQ={}", freq.as_str())
+                    .into()
+            );
+        }
+
+        // As in `UnitSchedule::new`, reject a month that can never satisfy `bymonthday` (e.g.
+        // `BYMONTH=2;BYMONTHDAY=30`, for which `RRuleIter::refill` would spin forever). Without
+        // `BYMONTH` all twelve months are checked, so `BYMONTHDAY=30` alone is rejected too.
+        let all_months: Vec<u32> = (1..13).collect();
+        let months_to_check: &[u32] = if bymonth.is_empty() { &all_months } else { &bymonth };
+        if !months_to_check.iter().all(|&m| mdays_possible_in_month(m, &bymonthday)) {
+            return Err("BYMONTH/BYMONTHDAY combination never matches".into());
+        }
+
+        Ok(RRule {
+            freq: freq,
+            interval: interval,
+            bymonth: bymonth,
+            bymonthday: bymonthday,
+            byday: byday,
+            byhour: byhour,
+            byminute: byminute,
+            bysecond: bysecond,
+            count: count,
+            until: until,
+        })
+    }
+
+    /// Enumerates occurrences strictly after `since`, which also supplies the defaults for any
+    /// unset `BYHOUR`/`BYMINUTE`/`BYSECOND`.
+    pub fn iter_since(&self, since: NaiveDateTime) -> RRuleIter {
+        RRuleIter {
+            rule: self,
+            period_start: since.date(),
+            pending: Vec::new(),
+            since: since,
+            dtstart: since,
+            remaining: self.count,
+        }
+    }
+}
+
+fn parse_csv<T, F: Fn(T) -> Option<T>>(s: &str, validate: F) -> Result<Vec<T>>
+    where T: ::std::str::FromStr
+{
+    s.split(',')
+        .map(|n| {
+            n.parse::<T>()
+                .map_err(|_| "expected a number".into())
+                .and_then(|n| validate(n).ok_or_else(|| "number out of range".into()))
+        })
+        .collect()
+}
+
+fn parse_weekday(s: &str) -> Result<Wday> {
+    // RFC 5545 allows an optional leading ordinal (e.g. `2MO`); not needed here, so it's dropped.
+    let s = s.trim_matches(|c: char| c == '+' || c == '-' || c.is_digit(10));
+
+    match s {
+        "SU" => Ok(0),
+        "MO" => Ok(1),
+        "TU" => Ok(2),
+        "WE" => Ok(3),
+        "TH" => Ok(4),
+        "FR" => Ok(5),
+        "SA" => Ok(6),
+        _ => Err(format!("unknown BYDAY: {}", s).into()),
+    }
+}
+
+fn parse_until(s: &str) -> Result<NaiveDateTime> {
+    if s.ends_with('Z') && s.len() == 16 {
+        NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%SZ").map_err(|e| e.to_string().into())
+    } else {
+        NaiveDateTime::parse_from_str(s, "%Y%m%dT%H%M%S").map_err(|e| e.to_string().into())
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let next = if month == 12 {
+        NaiveDate::from_ymd(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd(year, month + 1, 1)
+    };
+
+    next.signed_duration_since(NaiveDate::from_ymd(year, month, 1)).num_days() as u32
+}
+
+pub struct RRuleIter<'a> {
+    rule: &'a RRule,
+    period_start: NaiveDate,
+    pending: Vec<NaiveDateTime>,
+    since: NaiveDateTime,
+    /// The original start time passed to [`RRule::iter_since`] (never advances).
+    dtstart: NaiveDateTime,
+    remaining: Option<u32>,
+}
+
+impl<'a> RRuleIter<'a> {
+    /// Expands the `BY*` lists for `month` of `year` into sorted candidates.
+    fn candidates_for_month(&self, year: i32, month: u32) -> Vec<NaiveDateTime> {
+        let rule = self.rule;
+        let dim = days_in_month(year, month);
+
+        let mut mdays: Vec<u32> = if rule.bymonthday.is_empty() {
+            (1..dim + 1).collect()
+        } else {
+            rule.bymonthday.iter()
+                .filter_map(|&n| {
+                    let d = if n < 0 { dim as i32 + n + 1 } else { n };
+                    if 1 <= d && d <= dim as i32 { Some(d as u32) } else { None }
+                })
+                .collect()
+        };
+
+        // Per RFC 5545, FREQ=WEEKLY with no BYDAY defaults to DTSTART's weekday, not "every day".
+        let byday: Vec<Wday> = if rule.byday.is_empty() && rule.freq == Freq::Weekly {
+            vec![self.dtstart.weekday().num_days_from_sunday()]
+        } else {
+            rule.byday.clone()
+        };
+        if !byday.is_empty() {
+            mdays.retain(|&d| {
+                let wday = NaiveDate::from_ymd(year, month, d).weekday().num_days_from_sunday();
+                byday.contains(&wday)
+            });
+        }
+
+        // Unset BYHOUR/BYMINUTE/BYSECOND default to DTSTART's fields, not "every value".
+        let hours: Vec<u32> = if rule.byhour.is_empty() { vec![self.dtstart.hour()] } else { rule.byhour.clone() };
+        let mins: Vec<u32> = if rule.byminute.is_empty() { vec![self.dtstart.minute()] } else { rule.byminute.clone() };
+        let secs: Vec<u32> = if rule.bysecond.is_empty() { vec![self.dtstart.second()] } else { rule.bysecond.clone() };
+
+        let mut out = Vec::new();
+        for &d in &mdays {
+            let date = NaiveDate::from_ymd(year, month, d);
+            for &h in &hours {
+                for &m in &mins {
+                    for &s in &secs {
+                        out.push(date.and_hms(h, m, s));
+                    }
+                }
+            }
+        }
+        out.sort();
+        out
+    }
+
+    fn refill(&mut self) {
+        while self.pending.is_empty() {
+            let (year, month) = (self.period_start.year(), self.period_start.month());
+
+            // FREQ=YEARLY considers every listed month each year; other FREQs only the current
+            // period's month, if listed.
+            let months: Vec<u32> = if self.rule.bymonth.is_empty() {
+                vec![month]
+            } else if self.rule.freq == Freq::Yearly {
+                self.rule.bymonth.clone()
+            } else if self.rule.bymonth.contains(&month) {
+                vec![month]
+            } else {
+                Vec::new()
+            };
+
+            for m in months {
+                self.pending.extend(self.candidates_for_month(year, m));
+            }
+            self.pending.sort();
+            self.pending.retain(|tm| *tm > self.since);
+
+            self.period_start = match self.rule.freq {
+                Freq::Yearly => NaiveDate::from_ymd(year + self.rule.interval as i32, 1, 1),
+                _ => {
+                    let mut y = year;
+                    let mut mo = month + self.rule.interval;
+                    while mo > 12 {
+                        mo -= 12;
+                        y += 1;
+                    }
+                    NaiveDate::from_ymd(y, mo, 1)
+                }
+            };
+        }
+    }
+}
+
+impl<'a> Iterator for RRuleIter<'a> {
+    type Item = NaiveDateTime;
+
+    fn next(&mut self) -> Option<NaiveDateTime> {
+        if self.remaining == Some(0) {
+            return None;
+        }
+
+        self.refill();
+
+        let next = self.pending.remove(0);
+
+        if let Some(until) = self.rule.until {
+            if next >= until {
+                return None;
+            }
+        }
+
+        self.since = next;
+        self.remaining = self.remaining.map(|n| n - 1);
+
+        Some(next)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn ymdhms(y: i32, mon: u32, d: u32, h: u32, min: u32, sec: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(y, mon, d).and_hms(h, min, sec)
+    }
+
+    fn ymdhm(y: i32, mon: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        ymdhms(y, mon, d, h, min, 0)
+    }
+
+    /// A fake time zone with a one-hour DST gap (2017-03-12 02:00-03:00) and fold (11-05 01:00-02:00).
+    #[derive(Clone)]
+    struct FakeDstTz;
+
+    impl TimeZone for FakeDstTz {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            FakeDstTz
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms(0, 0, 0))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let gap_start = NaiveDate::from_ymd(2017, 3, 12).and_hms(2, 0, 0);
+            let gap_end = NaiveDate::from_ymd(2017, 3, 12).and_hms(3, 0, 0);
+            let fold_start = NaiveDate::from_ymd(2017, 11, 5).and_hms(1, 0, 0);
+            let fold_end = NaiveDate::from_ymd(2017, 11, 5).and_hms(2, 0, 0);
+
+            let standard = FixedOffset::west(5 * 3600);
+            let daylight = FixedOffset::west(4 * 3600);
+
+            if *local >= gap_start && *local < gap_end {
+                LocalResult::None
+            } else if *local >= fold_start && *local < fold_end {
+                LocalResult::Ambiguous(daylight, standard)
+            } else if *local >= gap_end && *local < fold_start {
+                LocalResult::Single(daylight)
+            } else {
+                LocalResult::Single(standard)
+            }
+        }
+
+        fn offset_from_utc_date(&self, _utc: &NaiveDate) -> FixedOffset {
+            FixedOffset::west(5 * 3600)
+        }
+
+        fn offset_from_utc_datetime(&self, _utc: &NaiveDateTime) -> FixedOffset {
+            FixedOffset::west(5 * 3600)
+        }
+    }
+
     #[test]
-    fn iter() {
-        fn ymdhm(y: i32, mon: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
-            NaiveDate::from_ymd(y, mon, d).and_hms(h, min, 0)
+    fn dst_gap_rolls_forward_to_boundary() {
+        let naive = NaiveDate::from_ymd(2017, 3, 12).and_hms(2, 30, 0);
+
+        // All three policies agree on a gap: roll forward to the first valid instant after it,
+        // not past it.
+        for &policy in &[DstPolicy::Earliest, DstPolicy::Latest, DstPolicy::Skip] {
+            let resolved = resolve_local(&FakeDstTz, naive, policy).unwrap();
+            assert_eq!(NaiveDate::from_ymd(2017, 3, 12).and_hms(3, 0, 0), resolved.naive_local());
         }
-        let us = UnitSchedule::new(vec![1, 0], vec![8, 12], vec![20, 40]).unwrap();
+    }
+
+    #[test]
+    fn dst_fold_honors_policy() {
+        let naive = NaiveDate::from_ymd(2017, 11, 5).and_hms(1, 30, 0);
+
+        let earliest = resolve_local(&FakeDstTz, naive, DstPolicy::Earliest).unwrap();
+        assert_eq!(FixedOffset::west(4 * 3600), earliest.offset().clone());
+        assert_eq!(naive, earliest.naive_local());
+
+        let latest = resolve_local(&FakeDstTz, naive, DstPolicy::Latest).unwrap();
+        assert_eq!(FixedOffset::west(5 * 3600), latest.offset().clone());
+        assert_eq!(naive, latest.naive_local());
+
+        assert_eq!(None, resolve_local(&FakeDstTz, naive, DstPolicy::Skip));
+    }
+
+    #[test]
+    fn timer_driver_fires_in_deadline_order() {
+        use futures::{executor, future, task};
+        use std::sync::mpsc;
+        use std::thread;
+
+        let driver = TimerDriver::global();
+        let (tx, rx) = mpsc::channel();
+
+        // Registered out of deadline order; the shared driver must still unpark nearest-first,
+        // regardless of registration order.
+        let driver_far = driver.clone();
+        let tx_far = tx.clone();
+        thread::spawn(move || {
+            // A task whose unpark wakes this thread (`task::park` needs a running task).
+            let task = executor::spawn(future::lazy(|| Ok::<_, ()>(task::park()))).wait_future().unwrap();
+            driver_far.schedule(Instant::now() + Duration::from_millis(150), task);
+            thread::park();
+            tx_far.send("far").unwrap();
+        });
+
+        let driver_near = driver.clone();
+        let tx_near = tx.clone();
+        thread::spawn(move || {
+            let task = executor::spawn(future::lazy(|| Ok::<_, ()>(task::park()))).wait_future().unwrap();
+            driver_near.schedule(Instant::now() + Duration::from_millis(30), task);
+            thread::park();
+            tx_near.send("near").unwrap();
+        });
+
+        assert_eq!("near", rx.recv().unwrap());
+        assert_eq!("far", rx.recv().unwrap());
+    }
+
+    #[test]
+    fn iter() {
+        let us = UnitSchedule::new(vec![1, 0], vec![8, 12], vec![20, 40], vec![], vec![], vec![]).unwrap();
 
         let mut iter = us.iter_since(ymdhm(2017, 2, 13, 8, 20)); // wday == 1
         assert_eq!(Some(ymdhm(2017, 2, 13,  8, 40)), iter.next());
@@ -363,18 +1344,63 @@ mod tests {
     #[test]
     fn new() {
         assert_eq!(
-            UnitSchedule::new(vec![1, 4, 0, 6], vec![8, 11, 23, 0], vec![0, 59]).unwrap(),
-            UnitSchedule::new(vec![0, 1, 4, 6], vec![0, 8, 11, 23], vec![0, 59]).unwrap()
+            UnitSchedule::new(vec![1, 4, 0, 6], vec![8, 11, 23, 0], vec![0, 59], vec![], vec![], vec![]).unwrap(),
+            UnitSchedule::new(vec![0, 1, 4, 6], vec![0, 8, 11, 23], vec![0, 59], vec![], vec![], vec![]).unwrap()
+        );
+
+        assert_eq!(
+            UnitSchedule::new(vec![                   ], vec![0], vec![0], vec![], vec![], vec![]).unwrap(),
+            UnitSchedule::new(vec![0, 1, 2, 3, 4, 5, 6], vec![0], vec![0], vec![], vec![], vec![]).unwrap()
         );
 
         assert_eq!(
-            UnitSchedule::new(vec![                   ], vec![0], vec![0]).unwrap(),
-            UnitSchedule::new(vec![0, 1, 2, 3, 4, 5, 6], vec![0], vec![0]).unwrap()
+            UnitSchedule::new(vec![0], vec![0], vec![0], vec![                ], vec![], vec![]).unwrap(),
+            UnitSchedule::new(vec![0], vec![0], vec![0], vec![1,2,3,4,5,6,7,8,9,10,11,12], vec![], vec![]).unwrap()
         );
 
-        assert!(UnitSchedule::new(vec![7], vec![ 0], vec![ 0]).is_none());
-        assert!(UnitSchedule::new(vec![0], vec![24], vec![ 0]).is_none());
-        assert!(UnitSchedule::new(vec![0], vec![ 0], vec![60]).is_none());
+        assert!(UnitSchedule::new(vec![7], vec![ 0], vec![ 0], vec![], vec![], vec![]).is_none());
+        assert!(UnitSchedule::new(vec![0], vec![24], vec![ 0], vec![], vec![], vec![]).is_none());
+        assert!(UnitSchedule::new(vec![0], vec![ 0], vec![60], vec![], vec![], vec![]).is_none());
+        assert!(UnitSchedule::new(vec![0], vec![ 0], vec![ 0], vec![13], vec![], vec![]).is_none());
+        assert!(UnitSchedule::new(vec![0], vec![ 0], vec![ 0], vec![0], vec![], vec![]).is_none());
+        assert!(UnitSchedule::new(vec![0], vec![ 0], vec![ 0], vec![], vec![0], vec![]).is_none());
+        assert!(UnitSchedule::new(vec![0], vec![ 0], vec![ 0], vec![], vec![32], vec![]).is_none());
+        assert!(UnitSchedule::new(vec![0], vec![ 0], vec![ 0], vec![], vec![::std::i32::MIN], vec![]).is_none());
+
+        // February can never land on the 30th.
+        assert!(UnitSchedule::new(vec![], vec![0], vec![0], vec![2], vec![30], vec![]).is_none());
+        // ...but `-1` (the last day) can, in February as in January, so this is fine.
+        assert!(UnitSchedule::new(vec![], vec![0], vec![0], vec![1, 2], vec![30, -1], vec![]).is_some());
+    }
+
+    #[test]
+    fn mdays() {
+        // The 1st and last day of every month, at 09:00.
+        let us = UnitSchedule::new(vec![], vec![9], vec![0], vec![], vec![1, -1], vec![]).unwrap();
+
+        let mut iter = us.iter_since(ymdhm(2017, 2, 1, 10, 0));
+        assert_eq!(Some(ymdhm(2017, 2, 28, 9, 0)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 3, 1, 9, 0)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 3, 31, 9, 0)), iter.next());
+    }
+
+    #[test]
+    fn secs() {
+        // Every 20 seconds, at 9:00 and 9:01.
+        let us = UnitSchedule::new(vec![], vec![9], vec![0, 1], vec![], vec![], vec![0, 20, 40]).unwrap();
+
+        let mut iter = us.iter_since(ymdhms(2017, 2, 13, 9, 0, 10));
+        assert_eq!(Some(ymdhms(2017, 2, 13, 9, 0, 20)), iter.next());
+        assert_eq!(Some(ymdhms(2017, 2, 13, 9, 0, 40)), iter.next());
+        assert_eq!(Some(ymdhms(2017, 2, 13, 9, 1, 0)), iter.next());
+        assert_eq!(Some(ymdhms(2017, 2, 13, 9, 1, 20)), iter.next());
+
+        // An empty `secs` still means "every minute", not "every second".
+        let us = UnitSchedule::new(vec![], vec![9], vec![0], vec![], vec![], vec![]).unwrap();
+        let mut iter = us.iter_since(ymdhms(2017, 2, 13, 8, 0, 0));
+        assert_eq!(Some(ymdhms(2017, 2, 13, 9, 0, 0)), iter.next());
+
+        assert!(UnitSchedule::new(vec![], vec![], vec![], vec![], vec![], vec![60]).is_none());
     }
 
     #[test]
@@ -388,4 +1414,133 @@ mod tests {
         assert!(parse_range("2- 3").is_err());
         assert!(parse_range("3-4-4").is_err());
     }
+
+    #[test]
+    fn schedule_phrases() {
+        assert_eq!(
+            parse_schedule("every weekday at 8:00 and 12:40").unwrap(),
+            UnitSchedule::new(vec![1, 2, 3, 4, 5], vec![8, 12], vec![0, 40], vec![], vec![], vec![]).unwrap()
+        );
+
+        assert_eq!(
+            parse_schedule("hourly").unwrap(),
+            UnitSchedule::new(vec![], vec![], vec![0], vec![], vec![], vec![]).unwrap()
+        );
+
+        assert_eq!(
+            parse_schedule("every 15 minutes on Mon,Wed").unwrap(),
+            UnitSchedule::new(vec![1, 3], vec![], vec![0, 15, 30, 45], vec![], vec![], vec![]).unwrap()
+        );
+
+        assert_eq!(
+            parse_schedule("daily at 6:30").unwrap(),
+            UnitSchedule::new(vec![], vec![6], vec![30], vec![], vec![], vec![]).unwrap()
+        );
+
+        assert_eq!(
+            parse_schedule("secondly").unwrap(),
+            UnitSchedule::new(vec![], vec![], vec![], vec![], vec![], (0..60).collect()).unwrap()
+        );
+
+        assert_eq!(
+            parse_schedule("every 15 seconds").unwrap(),
+            UnitSchedule::new(vec![], vec![], vec![], vec![], vec![], vec![0, 15, 30, 45]).unwrap()
+        );
+
+        assert_eq!(
+            parse_schedule("weekly on mon").unwrap(),
+            UnitSchedule::new(vec![1], vec![0], vec![0], vec![], vec![], vec![]).unwrap()
+        );
+
+        assert!(parse_schedule("").is_err());
+        assert!(parse_schedule("every 0 minutes").is_err());
+        assert!(parse_schedule("every 3 days").is_err());
+        assert!(parse_schedule("weekly at 25:00").is_err());
+        // Bare "weekly" has no weekday to anchor on, unlike "daily" -- it must be rejected rather
+        // than silently firing every day.
+        assert!(parse_schedule("weekly").is_err());
+        assert!(parse_schedule("bogus").is_err());
+    }
+
+    #[test]
+    fn rrule_weekly() {
+        let rr = RRule::from_rfc5545("FREQ=WEEKLY;BYDAY=MO,WE;BYHOUR=8,12;BYMINUTE=20,40").unwrap();
+
+        let mut iter = rr.iter_since(ymdhm(2017, 2, 13, 8, 20)); // Monday
+        assert_eq!(Some(ymdhm(2017, 2, 13,  8, 40)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 2, 13, 12, 20)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 2, 13, 12, 40)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 2, 15,  8, 20)), iter.next());
+    }
+
+    #[test]
+    fn rrule_weekly_defaults_byday_from_start_time() {
+        // No BYDAY: fires weekly on DTSTART's weekday, not daily.
+        let rr = RRule::from_rfc5545("FREQ=WEEKLY").unwrap();
+
+        let mut iter = rr.iter_since(ymdhm(2017, 2, 13, 8, 20)); // Monday
+        assert_eq!(Some(ymdhm(2017, 2, 20, 8, 20)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 2, 27, 8, 20)), iter.next());
+    }
+
+    #[test]
+    fn rrule_monthly_count() {
+        let rr = RRule::from_rfc5545("FREQ=MONTHLY;BYMONTHDAY=1,-1;BYHOUR=9;BYMINUTE=0;COUNT=2").unwrap();
+
+        let mut iter = rr.iter_since(ymdhm(2017, 2, 1, 10, 0));
+        assert_eq!(Some(ymdhm(2017, 2, 28, 9, 0)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 3, 1, 9, 0)), iter.next());
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    fn rrule_monthly_until() {
+        let rr = RRule::from_rfc5545("FREQ=MONTHLY;BYMONTHDAY=1;BYHOUR=9;BYMINUTE=0;UNTIL=20170401T090000Z").unwrap();
+
+        let mut iter = rr.iter_since(ymdhm(2017, 2, 1, 10, 0));
+        assert_eq!(Some(ymdhm(2017, 3, 1, 9, 0)), iter.next()); // before UNTIL
+        assert_eq!(None, iter.next()); // 2017-04-01 09:00 is at/after UNTIL
+    }
+
+    #[test]
+    fn rrule_parse_errors() {
+        assert!(RRule::from_rfc5545("BYDAY=MO").is_err());
+        assert!(RRule::from_rfc5545("FREQ=BOGUS").is_err());
+        // FREQ finer than daily isn't supported.
+        assert!(RRule::from_rfc5545("FREQ=HOURLY").is_err());
+        // INTERVAL isn't supported except with FREQ=YEARLY/MONTHLY.
+        assert!(RRule::from_rfc5545("FREQ=WEEKLY;INTERVAL=2").is_err());
+        assert!(RRule::from_rfc5545("FREQ=MONTHLY;INTERVAL=2").is_ok());
+        // BYMONTHDAY out of i32 range shouldn't panic in `.abs()`.
+        assert!(RRule::from_rfc5545("FREQ=MONTHLY;BYMONTHDAY=-2147483648").is_err());
+        // BYMONTH/BYMONTHDAY combinations that can never jointly match are rejected up front.
+        assert!(RRule::from_rfc5545("FREQ=MONTHLY;BYMONTH=2;BYMONTHDAY=30").is_err());
+        assert!(RRule::from_rfc5545("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30").is_err());
+        assert!(RRule::from_rfc5545("FREQ=MONTHLY;BYMONTHDAY=30").is_err()); // Feb is implied
+        assert!(RRule::from_rfc5545("FREQ=MONTHLY;BYMONTH=1,3;BYMONTHDAY=31").is_ok());
+    }
+
+    #[test]
+    fn rrule_defaults_by_fields_from_start_time() {
+        // No BYHOUR/BYMINUTE: fires once a day, at the start time's hour and minute, not every
+        // minute of every day.
+        let rr = RRule::from_rfc5545("FREQ=DAILY").unwrap();
+
+        let mut iter = rr.iter_since(ymdhm(2017, 2, 13, 8, 20));
+        assert_eq!(Some(ymdhm(2017, 2, 14, 8, 20)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 2, 15, 8, 20)), iter.next());
+    }
+
+    #[test]
+    fn rrule_monthly_bymonth_is_quarterly() {
+        // BYMONTH restricts a non-yearly FREQ to the listed months, not just FREQ=YEARLY.
+        let rr = RRule::from_rfc5545("FREQ=MONTHLY;BYMONTH=3,6,9,12;BYMONTHDAY=1;BYHOUR=9;BYMINUTE=0").unwrap();
+
+        let mut iter = rr.iter_since(ymdhm(2017, 1, 1, 0, 0));
+        assert_eq!(Some(ymdhm(2017, 3, 1, 9, 0)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 6, 1, 9, 0)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 9, 1, 9, 0)), iter.next());
+        assert_eq!(Some(ymdhm(2017, 12, 1, 9, 0)), iter.next());
+        assert_eq!(Some(ymdhm(2018, 3, 1, 9, 0)), iter.next());
+    }
 }